@@ -4,7 +4,7 @@ use jsonrpsee::tracing::trace;
 use std::future::Future;
 use std::time::Duration;
 use std::{
-    cmp::min,
+    cmp::{max, min},
     collections::VecDeque,
     pin::Pin,
     task::{Context, Poll},
@@ -16,29 +16,48 @@ pub(crate) type PinBoxFut<'a, T> =
 
 const TOO_MANY_LOGS_ERROR_MSG: [&str; 2] = ["exceeds the max limit of", "too large with more than"];
 
+/// Upper bound on the exponential backoff delay between retries of a failed request.
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
 /// A log query provides streaming access to historical logs via a paginated
-/// request. For streaming access to future logs, use [`Middleware::watch`] or
-/// [`Middleware::subscribe_logs`]
+/// request, and, when [`LogQuery::with_live_tail`] is enabled, seamlessly
+/// continues into a live subscription once the chain head is reached instead
+/// of ending the stream.
 pub struct LogQuery<'a, P> {
     provider: &'a Provider<P>,
     filter: Filter,
     from_block: Option<U64>,
 
     expected_page_size: u64,
-    /// It may be smaller than `expected_page_size` if the server cannot return all the logs.
+    /// AIMD-adjusted page size actually used for the next page. It grows additively towards
+    /// `expected_page_size` on a successful page and shrinks multiplicatively when the server
+    /// reports too many logs for the requested range.
     page_size: u64,
     current_logs: VecDeque<Log>,
     last_block: Option<U64>,
     state: LogQueryState<'a>,
     delay: Duration,
+    /// Whether to keep streaming newly produced logs once the historical
+    /// backfill reaches `last_block`, instead of ending the stream.
+    live_tail: bool,
+    /// Maximum number of retries for a transient provider error before giving up.
+    max_retries: u32,
+    /// Base delay used to compute the exponential backoff between retries.
+    backoff_base: Duration,
 }
 
 enum LogQueryState<'a> {
     Initial,
-    LoadLastBlock(PinBoxFut<'a, U64>),
-    /// `(from_block, get_logs_fut)`. `from_block` is used to resume if the request fails.
-    LoadLogs((Option<U64>, PinBoxFut<'a, Vec<Log>>)),
+    /// `(attempt, get_block_number_fut)`. `attempt` counts retries of a transient error.
+    LoadLastBlock((u32, PinBoxFut<'a, U64>)),
+    /// `(from_block, attempt, get_logs_fut)`. `from_block` is used to resume if the request
+    /// fails, and `attempt` counts retries of a transient error.
+    LoadLogs((Option<U64>, u32, PinBoxFut<'a, Vec<Log>>)),
     Consume,
+    /// `(attempt, get_block_number_fut)`. Polls for the newest block number once the historical
+    /// backfill is exhausted, so the stream can keep tailing new logs. `attempt` counts retries
+    /// of a transient error.
+    LiveTail((u32, PinBoxFut<'a, U64>)),
 }
 
 impl<'a, P> LogQuery<'a, P>
@@ -57,6 +76,9 @@ where
             last_block: None,
             state: LogQueryState::Initial,
             delay,
+            live_tail: false,
+            max_retries: 0,
+            backoff_base: Duration::from_millis(500),
         }
     }
 
@@ -66,6 +88,46 @@ where
         self.expected_page_size = page_size;
         self
     }
+
+    /// Once the historical pages up to `last_block` are drained, keep the
+    /// stream open and transition into tailing newly produced logs instead of
+    /// ending it. This only takes effect when the filter has no explicit
+    /// `to_block`; a bounded range query always terminates once consumed.
+    pub fn with_live_tail(mut self) -> Self {
+        self.live_tail = true;
+        self
+    }
+
+    /// Set the maximum number of retries for a transient provider error (anything other than a
+    /// "too many logs" response) before the error is surfaced on the stream. Defaults to `0`,
+    /// i.e. no retries.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the base delay for the exponential backoff applied between retries. The delay for
+    /// the `n`-th retry is `base * 2^n`, capped at [`MAX_RETRY_BACKOFF`].
+    pub fn with_backoff(mut self, base: Duration) -> Self {
+        self.backoff_base = base;
+        self
+    }
+
+    /// Delay before the `attempt`-th retry, growing exponentially and capped at
+    /// [`MAX_RETRY_BACKOFF`].
+    fn backoff_delay(&self, attempt: u32) -> Duration {
+        self.backoff_base
+            .saturating_mul(1u32.checked_shl(attempt).unwrap_or(u32::MAX))
+            .min(MAX_RETRY_BACKOFF)
+    }
+
+    /// `to_block` for the next page starting at `from_block`, capped at `last_block` if known.
+    fn page_to_block(&self, from_block: U64) -> U64 {
+        match self.last_block {
+            Some(last_block) => min(from_block + self.page_size - 1, last_block),
+            None => from_block + self.page_size - 1,
+        }
+    }
 }
 
 macro_rules! rewake_with_new_state {
@@ -106,7 +168,7 @@ where
                         tokio::time::sleep(delay).await;
                         provider.get_logs(&filter).await
                     });
-                    rewake_with_new_state!(ctx, self, LogQueryState::LoadLogs((None, fut)));
+                    rewake_with_new_state!(ctx, self, LogQueryState::LoadLogs((None, 0, fut)));
                 } else {
                     // if paginatable, load last block
                     let fut = match self.filter.get_to_block() {
@@ -115,10 +177,10 @@ where
                         // if to_block is none in filter, getLogs from from_block to latest block
                         _ => self.provider.get_block_number(),
                     };
-                    rewake_with_new_state!(ctx, self, LogQueryState::LoadLastBlock(fut));
+                    rewake_with_new_state!(ctx, self, LogQueryState::LoadLastBlock((0, fut)));
                 }
             }
-            LogQueryState::LoadLastBlock(fut) => {
+            LogQueryState::LoadLastBlock((attempt, fut)) => {
                 match futures_util::ready!(fut.as_mut().poll(ctx)) {
                     Ok(last_block) => {
                         trace!("log_query: last_block={}", last_block);
@@ -127,7 +189,7 @@ where
                         // this is okay because we will only enter this state when the filter is
                         // paginatable i.e. from block is set
                         let from_block = self.filter.get_from_block().unwrap();
-                        let to_block = min(from_block + self.page_size - 1, last_block);
+                        let to_block = self.page_to_block(from_block);
                         self.from_block = Some(to_block + 1);
 
                         let filter = self
@@ -145,27 +207,86 @@ where
                         rewake_with_new_state!(
                             ctx,
                             self,
-                            LogQueryState::LoadLogs((Some(from_block), fut))
+                            LogQueryState::LoadLogs((Some(from_block), 0, fut))
                         );
                     }
-                    Err(err) => Poll::Ready(Some(Err(LogQueryError::LoadLastBlockError(err)))),
+                    Err(err) => {
+                        if *attempt < self.max_retries {
+                            let next_attempt = *attempt + 1;
+                            let backoff = self.backoff_delay(next_attempt);
+                            let to_block = self.filter.get_to_block();
+                            let fut: PinBoxFut<'a, U64> = match to_block {
+                                Some(number) => Box::pin(async move {
+                                    tokio::time::sleep(backoff).await;
+                                    Ok(number)
+                                }),
+                                _ => {
+                                    let provider = self.provider;
+                                    Box::pin(async move {
+                                        tokio::time::sleep(backoff).await;
+                                        provider.get_block_number().await
+                                    })
+                                }
+                            };
+                            rewake_with_new_state!(
+                                ctx,
+                                self,
+                                LogQueryState::LoadLastBlock((next_attempt, fut))
+                            );
+                        }
+                        Poll::Ready(Some(Err(LogQueryError::LoadLastBlockError(err))))
+                    }
                 }
             }
-            LogQueryState::LoadLogs((from_block, fut)) => {
+            LogQueryState::LoadLogs((from_block, attempt, fut)) => {
                 match futures_util::ready!(fut.as_mut().poll(ctx)) {
                     Ok(logs) => {
                         self.current_logs = VecDeque::from(logs);
-                        self.page_size = self.expected_page_size;
+                        // additive increase: grow back towards the expected ceiling instead of
+                        // snapping straight to it, so a dense region doesn't cause another
+                        // immediate overshoot. The step is floored at 1 so a small configured
+                        // `expected_page_size` (< 8) can still recover after a shrink instead of
+                        // getting stuck forever on integer division.
+                        self.page_size = min(
+                            self.expected_page_size,
+                            self.page_size + max(1, self.expected_page_size / 8),
+                        );
                         rewake_with_new_state!(ctx, self, LogQueryState::Consume);
                     }
                     Err(err) => {
                         for msg in TOO_MANY_LOGS_ERROR_MSG.iter() {
                             if err.to_string().contains(msg) {
+                                // multiplicative decrease: shrink and retry the same from_block
                                 self.from_block = *from_block;
-                                self.page_size /= 2;
+                                self.page_size = max(1, self.page_size / 2);
                                 rewake_with_new_state!(ctx, self, LogQueryState::Consume);
                             }
                         }
+
+                        if *attempt < self.max_retries {
+                            let next_attempt = *attempt + 1;
+                            let backoff = self.backoff_delay(next_attempt);
+                            let from_block = *from_block;
+                            let filter = match from_block {
+                                Some(start) => self
+                                    .filter
+                                    .clone()
+                                    .from_block(start)
+                                    .to_block(self.page_to_block(start)),
+                                None => self.filter.clone(),
+                            };
+                            let provider = self.provider;
+                            let fut = Box::pin(async move {
+                                tokio::time::sleep(backoff).await;
+                                provider.get_logs(&filter).await
+                            });
+                            rewake_with_new_state!(
+                                ctx,
+                                self,
+                                LogQueryState::LoadLogs((from_block, next_attempt, fut))
+                            );
+                        }
+
                         Poll::Ready(Some(Err(LogQueryError::LoadLogsError(err))))
                     }
                 }
@@ -180,16 +301,20 @@ where
                         // load new logs if there are still more pages to go through
                         // can safely assume this will always be set in this state
                         let from_block = self.from_block.unwrap();
-                        let to_block = if let Some(l) = self.last_block {
-                            // if last_block is not none, only getLogs from to_block to last_block
-                            min(from_block + self.page_size - 1, l)
-                        } else {
-                            from_block + self.page_size - 1
-                        };
+                        let to_block = self.page_to_block(from_block);
 
                         // no more pages to load, and everything is consumed
                         // can safely assume this will always be set in this state
                         if from_block > self.last_block.unwrap() {
+                            if self.live_tail && self.filter.get_to_block().is_none() {
+                                let provider = self.provider;
+                                #[allow(clippy::redundant_async_block)]
+                                let fut = Box::pin(async move {
+                                    tokio::time::sleep(delay).await;
+                                    provider.get_block_number().await
+                                });
+                                rewake_with_new_state!(ctx, self, LogQueryState::LiveTail((0, fut)));
+                            }
                             return Poll::Ready(None);
                         }
                         // load next page
@@ -210,13 +335,226 @@ where
                         rewake_with_new_state!(
                             ctx,
                             self,
-                            LogQueryState::LoadLogs((Some(from_block), fut))
+                            LogQueryState::LoadLogs((Some(from_block), 0, fut))
                         );
                     }
                 } else {
                     Poll::Ready(log.map(Ok))
                 }
             }
+            LogQueryState::LiveTail((attempt, fut)) => {
+                match futures_util::ready!(fut.as_mut().poll(ctx)) {
+                    Ok(newest_block) => {
+                        // can safely assume this will always be set in this state
+                        let last_block = self.last_block.unwrap();
+                        if newest_block <= last_block {
+                            // chain head has not moved yet, keep polling on the delay interval
+                            let provider = self.provider;
+                            #[allow(clippy::redundant_async_block)]
+                            let fut = Box::pin(async move {
+                                tokio::time::sleep(delay).await;
+                                provider.get_block_number().await
+                            });
+                            rewake_with_new_state!(ctx, self, LogQueryState::LiveTail((0, fut)));
+                        }
+
+                        let from_block = last_block + 1;
+                        self.last_block = Some(newest_block);
+                        let to_block = self.page_to_block(from_block);
+                        self.from_block = Some(to_block + 1);
+
+                        let filter = self
+                            .filter
+                            .clone()
+                            .from_block(from_block)
+                            .to_block(to_block);
+                        let provider = self.provider;
+                        #[allow(clippy::redundant_async_block)]
+                        let fut = Box::pin(async move {
+                            tokio::time::sleep(delay).await;
+                            provider.get_logs(&filter).await
+                        });
+                        rewake_with_new_state!(
+                            ctx,
+                            self,
+                            LogQueryState::LoadLogs((Some(from_block), 0, fut))
+                        );
+                    }
+                    Err(err) => {
+                        if *attempt < self.max_retries {
+                            let next_attempt = *attempt + 1;
+                            let backoff = self.backoff_delay(next_attempt);
+                            let provider = self.provider;
+                            let fut = Box::pin(async move {
+                                tokio::time::sleep(backoff).await;
+                                provider.get_block_number().await
+                            });
+                            rewake_with_new_state!(
+                                ctx,
+                                self,
+                                LogQueryState::LiveTail((next_attempt, fut))
+                            );
+                        }
+                        Poll::Ready(Some(Err(LogQueryError::LoadLastBlockError(err))))
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use ethers::providers::{JsonRpcError, RpcError};
+    use ethers::types::Filter;
+    use futures_util::StreamExt;
+    use std::collections::VecDeque as Queue;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("{0}")]
+    struct FakeError(String);
+
+    impl RpcError for FakeError {
+        fn as_error_response(&self) -> Option<&JsonRpcError> {
+            None
+        }
+
+        fn as_serde_error(&self) -> Option<&serde_json::Error> {
+            None
+        }
+    }
+
+    /// Minimal `JsonRpcClient` backed by a FIFO queue of canned responses, so `LogQuery`'s state
+    /// machine can be driven deterministically without a live node.
+    #[derive(Clone, Debug, Default)]
+    struct FakeClient {
+        responses: Arc<Mutex<Queue<Result<serde_json::Value, String>>>>,
+    }
+
+    impl FakeClient {
+        fn push_ok<T: serde::Serialize>(&self, value: T) {
+            self.responses
+                .lock()
+                .unwrap()
+                .push_back(Ok(serde_json::to_value(value).unwrap()));
+        }
+
+        fn push_err(&self, message: impl Into<String>) {
+            self.responses.lock().unwrap().push_back(Err(message.into()));
+        }
+    }
+
+    #[async_trait]
+    impl JsonRpcClient for FakeClient {
+        type Error = FakeError;
+
+        async fn request<T, R>(&self, method: &str, _params: T) -> Result<R, Self::Error>
+        where
+            T: std::fmt::Debug + serde::Serialize + Send + Sync,
+            R: serde::de::DeserializeOwned + Send,
+        {
+            let response = self
+                .responses
+                .lock()
+                .unwrap()
+                .pop_front()
+                .unwrap_or_else(|| panic!("no mock response queued for `{method}`"));
+
+            match response {
+                Ok(value) => {
+                    Ok(serde_json::from_value(value).expect("mock response type mismatch"))
+                }
+                Err(message) => Err(FakeError(message)),
+            }
+        }
+    }
+
+    fn filter(from_block: u64, to_block: Option<u64>) -> Filter {
+        let filter = Filter::new().from_block(from_block);
+        match to_block {
+            Some(to_block) => filter.to_block(to_block),
+            None => filter,
+        }
+    }
+
+    #[test]
+    fn test_backoff_delay_grows_and_caps() {
+        let client = FakeClient::default();
+        let provider = Provider::new(client);
+        let filter = filter(0, Some(100));
+        let query = LogQuery::new(&provider, &filter, Duration::ZERO)
+            .with_backoff(Duration::from_millis(100));
+
+        assert_eq!(query.backoff_delay(0), Duration::from_millis(100));
+        assert_eq!(query.backoff_delay(1), Duration::from_millis(200));
+        assert_eq!(query.backoff_delay(2), Duration::from_millis(400));
+        // caps at MAX_RETRY_BACKOFF instead of overflowing
+        assert_eq!(query.backoff_delay(20), MAX_RETRY_BACKOFF);
+    }
+
+    #[tokio::test]
+    async fn test_page_size_shrinks_then_grows() {
+        let client = FakeClient::default();
+        let provider = Provider::new(client.clone());
+        let filter = filter(0, Some(100));
+        let mut query = LogQuery::new(&provider, &filter, Duration::ZERO).with_page_size(4);
+
+        // first page (0..=3) is rejected as too large: multiplicative decrease halves page_size
+        client.push_err("query returned more than 10000 results: too large with more than 1");
+        // retried page (0..=1) succeeds at the smaller size: additive increase grows it back up
+        client.push_ok(vec![Log::default()]);
+        // next page (2..=4) succeeds too, converging page_size back to the expected ceiling
+        client.push_ok(vec![Log::default()]);
+
+        assert!(query.next().await.is_some());
+        assert_eq!(query.page_size, 3);
+
+        assert!(query.next().await.is_some());
+        assert_eq!(query.page_size, 4);
+    }
+
+    #[tokio::test]
+    async fn test_retries_then_gives_up() {
+        let client = FakeClient::default();
+        let provider = Provider::new(client.clone());
+        // no `to_block` forces a real `get_block_number` request instead of a local future
+        let filter = filter(0, None);
+        let mut query = LogQuery::new(&provider, &filter, Duration::ZERO)
+            .with_max_retries(2)
+            .with_backoff(Duration::ZERO);
+
+        // initial attempt plus two retries, all failing
+        client.push_err("connection reset");
+        client.push_err("connection reset");
+        client.push_err("connection reset");
+
+        let item = query.next().await;
+        assert!(matches!(
+            item,
+            Some(Err(LogQueryError::LoadLastBlockError(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_backfill_then_live_tail_transition() {
+        let client = FakeClient::default();
+        let provider = Provider::new(client.clone());
+        let filter = filter(0, None);
+        let mut query = LogQuery::new(&provider, &filter, Duration::ZERO)
+            .with_page_size(100)
+            .with_live_tail();
+
+        client.push_ok(U64::from(5u64)); // last_block for the historical backfill
+        client.push_ok::<Vec<Log>>(vec![]); // backfill page [0, 5] is empty
+        client.push_ok(U64::from(6u64)); // live-tail poll observes a new block
+        client.push_ok(vec![Log::default()]); // the new page [6, 6] has one log
+
+        match query.next().await {
+            Some(Ok(log)) => assert_eq!(log, Log::default()),
+            other => panic!("expected a log from the live-tail page, got {other:?}"),
         }
     }
 }
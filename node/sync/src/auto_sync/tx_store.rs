@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::collections::HashSet;
 
 use anyhow::Result;
@@ -36,6 +37,19 @@ impl TxStore {
         format!("sync.manager.txs.{}.index2seq.{}", self.name, index)
     }
 
+    /// Zero-padded so that key order matches numeric `tx_seq` order, allowing pending tx_seqs to
+    /// be range-scanned in ascending order instead of only looked up by exact value.
+    fn key_sorted_seq(&self, tx_seq: u64) -> String {
+        format!("sync.manager.txs.{}.sorted.{:020}", self.name, tx_seq)
+    }
+
+    /// Exclusive upper bound for a `sorted` range scan over this `name` alone, so `range` never
+    /// wanders into another `TxStore`'s keys once this one runs out of entries: `~` (0x7e) sorts
+    /// after every digit, so it bounds the `sorted.` prefix without needing a real `tx_seq`.
+    fn key_sorted_seq_end(&self) -> String {
+        format!("sync.manager.txs.{}.sorted.~", self.name)
+    }
+
     fn index_of(&self, store: &dyn Store, tx_seq: u64) -> Result<Option<usize>> {
         store.get_config_decoded(&self.key_seq_to_index(tx_seq), DATA_DB_KEY)
     }
@@ -71,6 +85,7 @@ impl TxStore {
         tx.set_config(&self.key_index_to_seq(count), &tx_seq);
         tx.set_config(&self.key_seq_to_index(tx_seq), &count);
         tx.set_config(&self.key_count, &(count + 1));
+        tx.set_config(&self.key_sorted_seq(tx_seq), &tx_seq);
 
         if let Some(db_tx) = db_tx {
             db_tx.append(&mut tx);
@@ -81,6 +96,51 @@ impl TxStore {
         Ok(true)
     }
 
+    /// Add many tx_seqs in a single `ConfigTx` commit instead of one `exec_configs` per tx, to
+    /// cut write amplification when a burst of new data is announced. Returns the tx_seqs that
+    /// were actually added (excludes ones already present or repeated in `tx_seqs`).
+    ///
+    /// Unlike chaining `add` in a loop against one shared `db_tx`, this tracks `count` and
+    /// dedup state locally across the whole batch instead of re-reading `store`, which would
+    /// still report the pre-batch state for every write staged earlier in the same `ConfigTx`.
+    pub fn add_batch(
+        &self,
+        store: &dyn Store,
+        db_tx: Option<&mut ConfigTx>,
+        tx_seqs: impl IntoIterator<Item = u64>,
+    ) -> Result<Vec<u64>> {
+        let mut tx = ConfigTx::default();
+        let mut added = vec![];
+        let mut seen = HashSet::new();
+
+        let mut count = self.count(store)?;
+
+        for tx_seq in tx_seqs {
+            if !seen.insert(tx_seq) || self.has(store, tx_seq)? {
+                continue;
+            }
+
+            tx.set_config(&self.key_index_to_seq(count), &tx_seq);
+            tx.set_config(&self.key_seq_to_index(tx_seq), &count);
+            tx.set_config(&self.key_sorted_seq(tx_seq), &tx_seq);
+            count += 1;
+
+            added.push(tx_seq);
+        }
+
+        if !added.is_empty() {
+            tx.set_config(&self.key_count, &count);
+        }
+
+        if let Some(db_tx) = db_tx {
+            db_tx.append(&mut tx);
+        } else {
+            store.exec_configs(tx, DATA_DB_KEY)?;
+        }
+
+        Ok(added)
+    }
+
     pub fn random(&self, store: &dyn Store) -> Result<Option<u64>> {
         let count = self.count(store)?;
         if count == 0 {
@@ -93,6 +153,34 @@ impl TxStore {
         Ok(Some(tx_seq))
     }
 
+    /// Pending tx_seqs `>= start_seq`, in ascending order, up to `limit` entries.
+    ///
+    /// Backed by the `sorted` auxiliary index kept alongside `index2seq`/`seq2index`, so this
+    /// only touches the returned entries instead of scanning every pending tx_seq, unlike
+    /// `random`'s uniform pick over `index2seq` which gives no ordering guarantee.
+    pub fn range(&self, store: &dyn Store, start_seq: u64, limit: usize) -> Result<Vec<u64>> {
+        if limit == 0 {
+            return Ok(vec![]);
+        }
+
+        store.get_config_decoded_range(
+            &self.key_sorted_seq(start_seq),
+            &self.key_sorted_seq_end(),
+            limit,
+            DATA_DB_KEY,
+        )
+    }
+
+    /// Smallest pending tx_seq, if any.
+    pub fn min(&self, store: &dyn Store) -> Result<Option<u64>> {
+        Ok(self.range(store, 0, 1)?.into_iter().next())
+    }
+
+    /// Smallest pending tx_seq strictly greater than `tx_seq`, if any.
+    pub fn next_after(&self, store: &dyn Store, tx_seq: u64) -> Result<Option<u64>> {
+        Ok(self.range(store, tx_seq + 1, 1)?.into_iter().next())
+    }
+
     pub fn remove(
         &self,
         store: &dyn Store,
@@ -115,6 +203,9 @@ impl TxStore {
         // remove `seq2index` index
         tx.remove_config(&self.key_seq_to_index(tx_seq));
 
+        // remove `sorted` index
+        tx.remove_config(&self.key_sorted_seq(tx_seq));
+
         if index == count - 1 {
             // remove `index2seq` index for the last element
             tx.remove_config(&self.key_index_to_seq(index));
@@ -140,13 +231,120 @@ impl TxStore {
 
         Ok(true)
     }
+
+    /// Remove many tx_seqs in a single `ConfigTx` commit instead of one `exec_configs` per tx.
+    /// Returns the tx_seqs that were actually removed (excludes ones not present).
+    ///
+    /// The swap-remove bookkeeping (`count`, `index2seq`, `seq2index`) is tracked locally across
+    /// the batch via `index_of`/`seq_at` overlays, since `store` only reflects writes already
+    /// committed before this batch started, not the swaps staged earlier in the same `ConfigTx`.
+    pub fn remove_batch(
+        &self,
+        store: &dyn Store,
+        db_tx: Option<&mut ConfigTx>,
+        tx_seqs: impl IntoIterator<Item = u64>,
+    ) -> Result<Vec<u64>> {
+        let mut tx = ConfigTx::default();
+        let mut removed = vec![];
+
+        let mut count = self.count(store)?;
+        let mut index_of: HashMap<u64, usize> = HashMap::new();
+        let mut seq_at: HashMap<usize, u64> = HashMap::new();
+        let mut seen = HashSet::new();
+
+        for tx_seq in tx_seqs {
+            if !seen.insert(tx_seq) {
+                continue;
+            }
+
+            let index = match index_of.get(&tx_seq) {
+                Some(index) => Some(*index),
+                None => self.index_of(store, tx_seq)?,
+            };
+            let index = match index {
+                Some(val) => val,
+                None => continue,
+            };
+
+            assert!(count > 0, "data corruption");
+            count -= 1;
+
+            // remove `seq2index` and `sorted` index
+            tx.remove_config(&self.key_seq_to_index(tx_seq));
+            tx.remove_config(&self.key_sorted_seq(tx_seq));
+            index_of.remove(&tx_seq);
+
+            if index == count {
+                // remove `index2seq` index for the last element
+                tx.remove_config(&self.key_index_to_seq(index));
+                seq_at.remove(&index);
+            } else {
+                // swap `back` to the `removed` slot
+                let last_tx = match seq_at.get(&count) {
+                    Some(seq) => *seq,
+                    None => self.at(store, count)?.expect("data corruption"),
+                };
+
+                // update the `index2seq` for the removed element
+                tx.set_config(&self.key_index_to_seq(index), &last_tx);
+
+                // remove the last slot
+                tx.remove_config(&self.key_index_to_seq(count));
+
+                // update `seq2index` index for the last tx
+                tx.set_config(&self.key_seq_to_index(last_tx), &index);
+
+                index_of.insert(last_tx, index);
+                seq_at.insert(index, last_tx);
+                seq_at.remove(&count);
+            }
+
+            removed.push(tx_seq);
+        }
+
+        if !removed.is_empty() {
+            tx.set_config(&self.key_count, &count);
+        }
+
+        if let Some(db_tx) = db_tx {
+            db_tx.append(&mut tx);
+        } else {
+            store.exec_configs(tx, DATA_DB_KEY)?;
+        }
+
+        Ok(removed)
+    }
 }
 
-/// Cache the recent inserted tx in memory for random pick with priority.
+/// Default weight for a cache entry added without an explicit weight.
+const DEFAULT_WEIGHT: u64 = 1;
+
+/// Draw a tx_seq from the cache with probability proportional to its weight, via a
+/// cumulative-weight draw over `[0, total_weight)`.
+fn weighted_pick(cache: &HashMap<u64, u64>) -> Option<u64> {
+    let total_weight: u64 = cache.values().sum();
+    if total_weight == 0 {
+        return None;
+    }
+
+    let mut target = rand::thread_rng().gen_range(0..total_weight);
+    for (tx_seq, weight) in cache.iter() {
+        if target < *weight {
+            return Some(*tx_seq);
+        }
+        target -= *weight;
+    }
+
+    // unreachable as long as `total_weight` above was computed from the same `cache`
+    None
+}
+
+/// Cache the recent inserted tx in memory for weighted random pick with priority: entries with a
+/// higher weight (e.g. more recently announced data) are drawn more often.
 pub struct CachedTxStore {
     tx_store: TxStore,
     cache_cap: usize,
-    cache: RwLock<HashSet<u64>>,
+    cache: RwLock<HashMap<u64, u64>>,
 }
 
 impl CachedTxStore {
@@ -177,6 +375,17 @@ impl CachedTxStore {
         store: &dyn Store,
         db_tx: Option<&mut ConfigTx>,
         tx_seq: u64,
+    ) -> Result<bool> {
+        self.add_weighted(store, db_tx, tx_seq, None).await
+    }
+
+    /// Like [`Self::add`], but with an explicit cache weight instead of [`DEFAULT_WEIGHT`].
+    pub async fn add_weighted(
+        &self,
+        store: &dyn Store,
+        db_tx: Option<&mut ConfigTx>,
+        tx_seq: u64,
+        weight: Option<u64>,
     ) -> Result<bool> {
         if self.cache_cap == 0 {
             return self.tx_store.add(store, db_tx, tx_seq);
@@ -187,10 +396,10 @@ impl CachedTxStore {
         let added = self.tx_store.add(store, db_tx, tx_seq)?;
 
         if added {
-            cache.insert(tx_seq);
+            cache.insert(tx_seq, weight.unwrap_or(DEFAULT_WEIGHT));
 
             if cache.len() > self.cache_cap {
-                if let Some(popped) = cache.iter().choose(&mut rand::thread_rng()).cloned() {
+                if let Some(popped) = cache.keys().choose(&mut rand::thread_rng()).cloned() {
                     cache.remove(&popped);
                 }
             }
@@ -199,6 +408,51 @@ impl CachedTxStore {
         Ok(added)
     }
 
+    /// Add many tx_seqs in a single `ConfigTx` commit instead of one `exec_configs` per tx, to
+    /// cut write amplification when a burst of new data is announced. Returns the tx_seqs that
+    /// were actually added (excludes ones already present).
+    pub async fn add_batch(
+        &self,
+        store: &dyn Store,
+        db_tx: Option<&mut ConfigTx>,
+        items: impl IntoIterator<Item = (u64, Option<u64>)>,
+    ) -> Result<Vec<u64>> {
+        let items: Vec<(u64, Option<u64>)> = items.into_iter().collect();
+
+        if self.cache_cap == 0 {
+            return self
+                .tx_store
+                .add_batch(store, db_tx, items.into_iter().map(|(tx_seq, _)| tx_seq));
+        }
+
+        let mut cache = self.cache.write().await;
+
+        let added = self
+            .tx_store
+            .add_batch(store, db_tx, items.iter().map(|(tx_seq, _)| *tx_seq))?;
+
+        if !added.is_empty() {
+            let mut weights = HashMap::new();
+            for (tx_seq, weight) in &items {
+                weights
+                    .entry(*tx_seq)
+                    .or_insert_with(|| weight.unwrap_or(DEFAULT_WEIGHT));
+            }
+
+            for tx_seq in &added {
+                cache.insert(*tx_seq, weights[tx_seq]);
+
+                if cache.len() > self.cache_cap {
+                    if let Some(popped) = cache.keys().choose(&mut rand::thread_rng()).cloned() {
+                        cache.remove(&popped);
+                    }
+                }
+            }
+        }
+
+        Ok(added)
+    }
+
     pub async fn random(&self, store: &dyn Store) -> Result<Option<u64>> {
         if self.cache_cap == 0 {
             return self.tx_store.random(store);
@@ -206,7 +460,7 @@ impl CachedTxStore {
 
         let cache = self.cache.read().await;
 
-        if let Some(v) = cache.iter().choose(&mut rand::thread_rng()).cloned() {
+        if let Some(v) = weighted_pick(&cache) {
             return Ok(Some(v));
         }
 
@@ -223,7 +477,7 @@ impl CachedTxStore {
             return self.tx_store.remove(store, db_tx, tx_seq);
         }
 
-        let mut cache: tokio::sync::RwLockWriteGuard<'_, HashSet<u64>> = self.cache.write().await;
+        let mut cache = self.cache.write().await;
 
         let removed = self.tx_store.remove(store, db_tx, tx_seq)?;
 
@@ -233,13 +487,36 @@ impl CachedTxStore {
 
         Ok(removed)
     }
+
+    /// Remove many tx_seqs in a single `ConfigTx` commit instead of one `exec_configs` per tx.
+    /// Returns the tx_seqs that were actually removed (excludes ones not present).
+    pub async fn remove_batch(
+        &self,
+        store: &dyn Store,
+        db_tx: Option<&mut ConfigTx>,
+        tx_seqs: impl IntoIterator<Item = u64>,
+    ) -> Result<Vec<u64>> {
+        if self.cache_cap == 0 {
+            return self.tx_store.remove_batch(store, db_tx, tx_seqs);
+        }
+
+        let mut cache = self.cache.write().await;
+
+        let removed = self.tx_store.remove_batch(store, db_tx, tx_seqs)?;
+
+        for tx_seq in &removed {
+            cache.remove(tx_seq);
+        }
+
+        Ok(removed)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::test_util::tests::TestStoreRuntime;
 
-    use super::TxStore;
+    use super::{CachedTxStore, TxStore};
 
     #[test]
     fn test_add() {
@@ -337,4 +614,77 @@ mod tests {
         assert_eq!(tx_store.at(&store, 2).unwrap(), Some(3));
         assert_eq!(tx_store.at(&store, 3).unwrap(), None);
     }
+
+    #[test]
+    fn test_range() {
+        let store = TestStoreRuntime::new_store();
+        let tx_store = TxStore::new("foo");
+
+        assert_eq!(tx_store.range(&store, 0, 10).unwrap(), Vec::<u64>::new());
+
+        // add out of order, and with a gap, to check the sorted index rather than insertion order
+        assert!(tx_store.add(&store, None, 5).unwrap());
+        assert!(tx_store.add(&store, None, 1).unwrap());
+        assert!(tx_store.add(&store, None, 3).unwrap());
+
+        assert_eq!(tx_store.range(&store, 0, 10).unwrap(), vec![1, 3, 5]);
+        assert_eq!(tx_store.range(&store, 2, 10).unwrap(), vec![3, 5]);
+        assert_eq!(tx_store.range(&store, 2, 1).unwrap(), vec![3]);
+        assert_eq!(tx_store.range(&store, 6, 10).unwrap(), Vec::<u64>::new());
+
+        assert!(tx_store.remove(&store, None, 3).unwrap());
+        assert_eq!(tx_store.range(&store, 0, 10).unwrap(), vec![1, 5]);
+    }
+
+    #[test]
+    fn test_min_and_next_after() {
+        let store = TestStoreRuntime::new_store();
+        let tx_store = TxStore::new("foo");
+
+        assert_eq!(tx_store.min(&store).unwrap(), None);
+        assert_eq!(tx_store.next_after(&store, 0).unwrap(), None);
+
+        assert!(tx_store.add(&store, None, 5).unwrap());
+        assert!(tx_store.add(&store, None, 1).unwrap());
+        assert!(tx_store.add(&store, None, 3).unwrap());
+
+        assert_eq!(tx_store.min(&store).unwrap(), Some(1));
+        assert_eq!(tx_store.next_after(&store, 1).unwrap(), Some(3));
+        assert_eq!(tx_store.next_after(&store, 3).unwrap(), Some(5));
+        assert_eq!(tx_store.next_after(&store, 5).unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_cached_add_batch_and_remove_batch() {
+        let store = TestStoreRuntime::new_store();
+        let cached = CachedTxStore::new("foo", 8);
+
+        let added = cached
+            .add_batch(&store, None, [(1, None), (2, Some(5)), (3, None), (1, None)])
+            .await
+            .unwrap();
+        // tx_seq 1 is only added once
+        assert_eq!(added, vec![1, 2, 3]);
+        assert_eq!(cached.count(&store).await.unwrap(), (3, 3));
+
+        let removed = cached.remove_batch(&store, None, [2, 3, 3]).await.unwrap();
+        assert_eq!(removed, vec![2, 3]);
+        assert_eq!(cached.count(&store).await.unwrap(), (1, 1));
+        assert!(cached.has(&store, 1).unwrap());
+        assert!(!cached.has(&store, 2).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_cached_weighted_random() {
+        let store = TestStoreRuntime::new_store();
+        let cached = CachedTxStore::new("foo", 8);
+
+        // a zero-weight entry should never be drawn while a weighted one is present
+        assert!(cached.add_weighted(&store, None, 1, Some(0)).await.unwrap());
+        assert!(cached.add_weighted(&store, None, 2, Some(1)).await.unwrap());
+
+        for _ in 0..20 {
+            assert_eq!(cached.random(&store).await.unwrap(), Some(2));
+        }
+    }
 }